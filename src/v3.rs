@@ -10,29 +10,45 @@ use ring::signature::{self, KeyPair};
 use chrono::{Utc, DateTime};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct Transaction {
+struct UnsignedTransaction {
     sender_address: Vec<u8>,
     receiver_address: Vec<u8>,
     value: f32,
-    sign: Vec<u8>,
 }
 
 trait Transact {
     fn str_data(&self) -> String;
 }
 
-impl Transact for Transaction {
+impl Transact for UnsignedTransaction {
     fn str_data(&self) -> String {
-        let t = Transaction {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SignedTransaction {
+    sender_address: Vec<u8>,
+    receiver_address: Vec<u8>,
+    value: f32,
+    sign: Vec<u8>,
+}
+
+impl SignedTransaction {
+    fn unsigned(&self) -> UnsignedTransaction {
+        UnsignedTransaction {
             sender_address: self.sender_address.clone(),
             receiver_address: self.receiver_address.clone(),
             value: self.value,
-            sign: [].to_vec(),
-        };
-        serde_json::to_string(&t).unwrap()
+        }
     }
 }
 
+// producible only by a successful verify_transaction, so `TimestampServer::generate_block`
+// can require `&[VerifiedTransaction]` instead of re-checking signatures itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VerifiedTransaction(SignedTransaction);
+
 #[derive(Debug, Serialize, Deserialize)]
 struct Wallet {
     private_key: Vec<u8>,
@@ -52,17 +68,17 @@ impl Wallet {
 }
 
 trait Deal {
-    fn sign_transaction(&self, transaction: &Transaction) -> Transaction;
-    fn send(&self, receiver_address: &[u8], value: f32) -> Transaction;
+    fn sign_transaction(&self, transaction: &UnsignedTransaction) -> SignedTransaction;
+    fn send(&self, receiver_address: &[u8], value: f32) -> SignedTransaction;
 }
 
 impl Deal for Wallet {
-    fn sign_transaction(&self, transaction: &Transaction) -> Transaction {
+    fn sign_transaction(&self, transaction: &UnsignedTransaction) -> SignedTransaction {
         // generate signer from self private key
         let key_pair = signature::Ed25519KeyPair::from_pkcs8(&self.private_key).unwrap();
         // hash the transaction
         let h = digest::digest(&digest::SHA512, &transaction.str_data().as_bytes());
-        Transaction {
+        SignedTransaction {
             sender_address: transaction.sender_address.clone(),
             receiver_address: transaction.receiver_address.clone(),
             value: transaction.value,
@@ -70,12 +86,11 @@ impl Deal for Wallet {
         }
     }
 
-    fn send(&self, receiver_address: &[u8], value: f32) -> Transaction {
-        let t = Transaction {
+    fn send(&self, receiver_address: &[u8], value: f32) -> SignedTransaction {
+        let t = UnsignedTransaction {
             sender_address: self.address.clone(),
             receiver_address: receiver_address.to_vec(),
             value: value,
-            sign: [].to_vec(),
         };
         self.sign_transaction(&t)
     }
@@ -84,7 +99,7 @@ impl Deal for Wallet {
 #[derive(Debug, Serialize, Deserialize)]
 struct Block {
     time: DateTime<Utc>,
-    transactions: Vec<Transaction>,
+    transactions: Vec<VerifiedTransaction>,
     previous_hash: Vec<u8>,
     sign: Vec<u8>,
 }
@@ -135,11 +150,11 @@ impl TimestampServer {
 }
 
 trait BlockGenerator {
-    fn generate_block(&mut self, transaction: &[Transaction]);
+    fn generate_block(&mut self, transactions: &[VerifiedTransaction]);
 }
 
 impl BlockGenerator for TimestampServer {
-    fn generate_block(&mut self, transactions: &[Transaction]) {
+    fn generate_block(&mut self, transactions: &[VerifiedTransaction]) {
         let mut tlist = Vec::new();
         tlist.extend_from_slice(transactions);
         let tlist = tlist;
@@ -163,33 +178,29 @@ impl BlockGenerator for TimestampServer {
     }
 }
 
-fn verify_transaction(transaction: &Transaction) -> Result<(), Error> {
+fn verify_transaction(transaction: SignedTransaction) -> Result<VerifiedTransaction, Error> {
     if transaction.sign.is_empty() { return Err(anyhow!("transaction's sign is empty.")); }
 
     // hash the transaction
-    let h = digest::digest(&digest::SHA512, &transaction.str_data().as_bytes());
+    let h = digest::digest(&digest::SHA512, &transaction.unsigned().str_data().as_bytes());
     // generate verifier with public key
     let peer_public_key_bytes = &transaction.sender_address;
     let peer_public_key = signature::UnparsedPublicKey::new(&signature::ED25519, peer_public_key_bytes);
     // is the signature correct?
     match peer_public_key.verify(h.as_ref(), &transaction.sign) {
-        Ok(_) => Ok(()),
+        Ok(_) => Ok(VerifiedTransaction(transaction)),
         Err(_) => Err(anyhow!("invalid sign."))
     }
 }
 
 fn verify_block(previous_block: &Block, block: &Block, timestamp_server_publickey: &[u8]) -> bool {
+    // every transaction in `block.transactions` is already a `VerifiedTransaction`,
+    // so signature validity no longer needs to be re-checked here.
     let is_correct_hash = previous_block.hash() == block.previous_hash;
-    let is_correct_transactions = 
-        block.transactions
-        .iter()
-        .filter(|x| verify_transaction(&x).is_err())
-        .collect::<Vec<&Transaction>>()
-        .is_empty();
     let h = block.hash();
     let peer_public_key = signature::UnparsedPublicKey::new(&signature::ED25519, timestamp_server_publickey);
     match peer_public_key.verify(h.as_ref(), &block.sign) {
-        Ok(_) => is_correct_hash && is_correct_transactions,
+        Ok(_) => is_correct_hash,
         Err(_) => false
     }
 }
@@ -209,8 +220,8 @@ fn main() {
     let bob = Wallet::new();
 
     let mut transactions = Vec::new();
-    transactions.push(alice.send(&bob.address, 5.0));
-    transactions.push(bob.send(&alice.address, 7.0));
+    transactions.push(verify_transaction(alice.send(&bob.address, 5.0)).unwrap());
+    transactions.push(verify_transaction(bob.send(&alice.address, 7.0)).unwrap());
 
     timestamp_server.generate_block(&transactions);
 
@@ -232,9 +243,8 @@ fn main() {
 
 
     // ブロックの一つを書き換えてみる（verifyがfalseになる）
-    timestamp_server.block_chain[3].transactions[0].value = 1.0;
+    timestamp_server.block_chain[3].transactions[0].0.value = 1.0;
     let verify_result = verify_blockchain(&timestamp_server.block_chain, &timestamp_server.public_key);
     println!("timestamp server: {:?}", &timestamp_server);
     println!("verify: {:?}", verify_result);
 }
-
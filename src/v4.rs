@@ -2,6 +2,7 @@ extern crate anyhow;
 extern crate serde;
 extern crate ring;
 extern crate chrono;
+extern crate bincode;
 
 use anyhow::{anyhow, Error, Result};
 use serde::{Deserialize, Serialize};
@@ -17,19 +18,21 @@ struct Transaction {
     sign: Vec<u8>,
 }
 
-trait Transact {
-    fn str_data(&self) -> String;
-}
-
-impl Transact for Transaction {
-    fn str_data(&self) -> String {
-        let t = Transaction {
-            sender_address: self.sender_address.clone(),
-            receiver_address: self.receiver_address.clone(),
+impl Transaction {
+    // the deterministic bytes that get signed/hashed - bincode avoids the
+    // allocation and formatting non-determinism of round-tripping through JSON
+    fn canonical_bytes(&self) -> Vec<u8> {
+        #[derive(Serialize)]
+        struct Unsigned<'a> {
+            sender_address: &'a [u8],
+            receiver_address: &'a [u8],
+            value: f32,
+        }
+        bincode::serialize(&Unsigned {
+            sender_address: &self.sender_address,
+            receiver_address: &self.receiver_address,
             value: self.value,
-            sign: [].to_vec(),
-        };
-        serde_json::to_string(&t).unwrap()
+        }).unwrap()
     }
 }
 
@@ -61,7 +64,7 @@ impl Deal for Wallet {
         // generate signer from self private key
         let key_pair = signature::Ed25519KeyPair::from_pkcs8(&self.private_key).unwrap();
         // hash the transaction
-        let h = digest::digest(&digest::SHA512, &transaction.str_data().as_bytes());
+        let h = digest::digest(&digest::SHA512, &transaction.canonical_bytes());
         Transaction {
             sender_address: transaction.sender_address.clone(),
             receiver_address: transaction.receiver_address.clone(),
@@ -86,7 +89,31 @@ struct Block {
     time: DateTime<Utc>,
     transactions: Vec<Transaction>,
     previous_hash: Vec<u8>,
-    nonce: u32,
+    nonce: u64,
+    difficulty: usize,
+    merkle_root: Vec<u8>,
+}
+
+impl Block {
+    // only the header: the transaction list itself is covered by `merkle_root`,
+    // so `mine`'s per-nonce hash no longer has to reserialize every transaction
+    fn canonical_bytes(&self) -> Vec<u8> {
+        #[derive(Serialize)]
+        struct Header<'a> {
+            time: DateTime<Utc>,
+            previous_hash: &'a [u8],
+            nonce: u64,
+            difficulty: usize,
+            merkle_root: &'a [u8],
+        }
+        bincode::serialize(&Header {
+            time: self.time,
+            previous_hash: &self.previous_hash,
+            nonce: self.nonce,
+            difficulty: self.difficulty,
+            merkle_root: &self.merkle_root,
+        }).unwrap()
+    }
 }
 
 trait HashBlock {
@@ -95,24 +122,43 @@ trait HashBlock {
 
 impl HashBlock for Block {
     fn hash(&self) -> Vec<u8> {
-        let b = Block {
-            time: self.time,
-            transactions: self.transactions.clone(),
-            previous_hash: self.previous_hash.clone(),
-            nonce: self.nonce,
-        };
-        let s = serde_json::to_string(&b).unwrap();
-        digest::digest(&digest::SHA512, s.as_bytes()).as_ref().to_owned()
+        digest::digest(&digest::SHA512, &self.canonical_bytes()).as_ref().to_owned()
     }
 }
 
+// the SHA-512 Merkle tree over a block's transactions, duplicating the last
+// leaf whenever a level has an odd number of nodes
+fn merkle_root(transactions: &[Transaction]) -> Vec<u8> {
+    if transactions.is_empty() {
+        return digest::digest(&digest::SHA512, &[]).as_ref().to_owned();
+    }
+    let mut level: Vec<Vec<u8>> = transactions
+        .iter()
+        .map(|t| digest::digest(&digest::SHA512, &t.canonical_bytes()).as_ref().to_owned())
+        .collect();
+    while level.len() > 1 {
+        if level.len() % 2 == 1 {
+            level.push(level.last().unwrap().clone());
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| {
+                let mut combined = pair[0].clone();
+                combined.extend_from_slice(&pair[1]);
+                digest::digest(&digest::SHA512, &combined).as_ref().to_owned()
+            })
+            .collect();
+    }
+    level.remove(0)
+}
+
 type BlockChain = Vec<Block>;
 
 fn verify_transaction(transaction: &Transaction) -> Result<(), Error> {
     if transaction.sign.is_empty() { return Err(anyhow!("transaction's sign is empty.")); }
 
     // hash the transaction
-    let h = digest::digest(&digest::SHA512, &transaction.str_data().as_bytes());
+    let h = digest::digest(&digest::SHA512, &transaction.canonical_bytes());
     // generate verifier with public key
     let peer_public_key_bytes = &transaction.sender_address;
     let peer_public_key = signature::UnparsedPublicKey::new(&signature::ED25519, peer_public_key_bytes);
@@ -125,34 +171,46 @@ fn verify_transaction(transaction: &Transaction) -> Result<(), Error> {
 
 fn verify_block(previous_block: &Block, block: &Block) -> bool {
     let is_correct_hash = previous_block.hash() == block.previous_hash;
-    let is_correct_transactions = 
+    let is_correct_transactions =
         block.transactions
         .iter()
         .filter(|x| verify_transaction(&x).is_err())
         .collect::<Vec<&Transaction>>()
         .is_empty();
+    let is_correct_merkle_root = merkle_root(&block.transactions) == block.merkle_root;
     let is_correct_proof = valid_proof(block).is_ok();
-    is_correct_hash && is_correct_transactions && is_correct_proof
+    is_correct_hash && is_correct_transactions && is_correct_merkle_root && is_correct_proof
 }
 
-const DIFFICULTY: usize = 1;
+// difficulty is a count of required leading zero *bits*, not whole zero bytes,
+// so it can be tuned in fine-grained steps (e.g. 20 bits) instead of jumping by 8
 fn valid_proof(block: &Block) -> Result<(), Error> {
     let h = block.hash();
-    for i in 0..DIFFICULTY {
+    let full_bytes = block.difficulty / 8;
+    let remaining_bits = block.difficulty % 8;
+    for i in 0..full_bytes {
         if h[i] != 0 {
             return Err(anyhow!("invalid nonce."));
         }
     }
+    if remaining_bits > 0 && h[full_bytes] >> (8 - remaining_bits) != 0 {
+        return Err(anyhow!("invalid nonce."));
+    }
     Ok(())
 }
 
 fn mine(block: &Block) -> Block {
-    let mut nonce: u32 = 0;
+    let mut nonce: u64 = 0;
+    // computed once up front - transactions don't change between nonce attempts,
+    // so the hash loop below only ever re-serializes the (small) block header
+    let merkle_root = merkle_root(&block.transactions);
     let mut b = Block {
         time: block.time,
         transactions: block.transactions.clone(),
         previous_hash: block.previous_hash.clone(),
         nonce: nonce,
+        difficulty: block.difficulty,
+        merkle_root,
     };
     eprint!("\rMining Block (nonce = {:?}, hash = {:?})\x1b[0K", nonce, b.hash());
     while valid_proof(&b).is_err() {
@@ -173,6 +231,8 @@ fn main() {
         transactions: Vec::new(),
         previous_hash: [].to_vec(),
         nonce: 0,
+        difficulty: 0,
+        merkle_root: merkle_root(&[]),
     };
     block_chain.push(genesis);
 
@@ -188,12 +248,14 @@ fn main() {
         transactions: transactions,
         previous_hash: previous_hash,
         nonce: 0,
+        difficulty: 20,
+        merkle_root: Vec::new(),
     };
     let block = mine(&t_block);
     let verify_result = verify_block(block_chain.last().unwrap(), &block);
     println!("Block nonce: {:?}", block.nonce);
     println!("Block hash: {:?}", block.hash());
-    println!("Difficulty: {:?}", DIFFICULTY);
+    println!("Difficulty: {:?}", block.difficulty);
     println!("verify: {:?}", verify_result);
 
     block_chain.push(block);
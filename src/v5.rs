@@ -0,0 +1,305 @@
+extern crate anyhow;
+extern crate serde;
+extern crate ring;
+extern crate chrono;
+
+use anyhow::{anyhow, Error, Result};
+use serde::{Deserialize, Serialize};
+use ring::{rand, digest};
+use ring::signature::{self, KeyPair};
+use chrono::{Utc, DateTime};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UnsignedTransaction {
+    sender_address: Vec<u8>,
+    receiver_address: Vec<u8>,
+    value: f32,
+}
+
+trait Transact {
+    fn str_data(&self) -> String;
+}
+
+impl Transact for UnsignedTransaction {
+    fn str_data(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SignedTransaction {
+    sender_address: Vec<u8>,
+    receiver_address: Vec<u8>,
+    value: f32,
+    sign: Vec<u8>,
+}
+
+impl SignedTransaction {
+    fn unsigned(&self) -> UnsignedTransaction {
+        UnsignedTransaction {
+            sender_address: self.sender_address.clone(),
+            receiver_address: self.receiver_address.clone(),
+            value: self.value,
+        }
+    }
+}
+
+// producible only by a successful verify_transaction, so whichever validator the
+// `StakePool` selects can build a block straight from `&[VerifiedTransaction]`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VerifiedTransaction(SignedTransaction);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Wallet {
+    private_key: Vec<u8>,
+    address: Vec<u8>,
+}
+
+impl Wallet {
+    fn new() -> Wallet {
+        let rng = rand::SystemRandom::new();
+        let pkcs8_bytes = signature::Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key_pair = signature::Ed25519KeyPair::from_pkcs8(pkcs8_bytes.as_ref()).unwrap();
+        Wallet {
+            private_key: pkcs8_bytes.as_ref().to_owned(),
+            address: key_pair.public_key().as_ref().to_owned(),
+        }
+    }
+}
+
+trait Deal {
+    fn sign_transaction(&self, transaction: &UnsignedTransaction) -> SignedTransaction;
+    fn send(&self, receiver_address: &[u8], value: f32) -> SignedTransaction;
+}
+
+impl Deal for Wallet {
+    fn sign_transaction(&self, transaction: &UnsignedTransaction) -> SignedTransaction {
+        // generate signer from self private key
+        let key_pair = signature::Ed25519KeyPair::from_pkcs8(&self.private_key).unwrap();
+        // hash the transaction
+        let h = digest::digest(&digest::SHA512, &transaction.str_data().as_bytes());
+        SignedTransaction {
+            sender_address: transaction.sender_address.clone(),
+            receiver_address: transaction.receiver_address.clone(),
+            value: transaction.value,
+            sign: key_pair.sign(h.as_ref()).as_ref().to_vec(),
+        }
+    }
+
+    fn send(&self, receiver_address: &[u8], value: f32) -> SignedTransaction {
+        let t = UnsignedTransaction {
+            sender_address: self.address.clone(),
+            receiver_address: receiver_address.to_vec(),
+            value: value,
+        };
+        self.sign_transaction(&t)
+    }
+}
+
+fn verify_transaction(transaction: SignedTransaction) -> Result<VerifiedTransaction, Error> {
+    if transaction.sign.is_empty() { return Err(anyhow!("transaction's sign is empty.")); }
+
+    // hash the transaction
+    let h = digest::digest(&digest::SHA512, &transaction.unsigned().str_data().as_bytes());
+    // generate verifier with public key
+    let peer_public_key_bytes = &transaction.sender_address;
+    let peer_public_key = signature::UnparsedPublicKey::new(&signature::ED25519, peer_public_key_bytes);
+    // is the signature correct?
+    match peer_public_key.verify(h.as_ref(), &transaction.sign) {
+        Ok(_) => Ok(VerifiedTransaction(transaction)),
+        Err(_) => Err(anyhow!("invalid sign."))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Block {
+    time: DateTime<Utc>,
+    transactions: Vec<VerifiedTransaction>,
+    previous_hash: Vec<u8>,
+    validator: Vec<u8>,
+    sign: Vec<u8>,
+}
+
+trait HashBlock {
+    fn hash(&self) -> Vec<u8>;
+}
+
+impl HashBlock for Block {
+    fn hash(&self) -> Vec<u8> {
+        let b = Block {
+            time: self.time,
+            transactions: self.transactions.clone(),
+            previous_hash: self.previous_hash.clone(),
+            validator: self.validator.clone(),
+            sign: [].to_vec(),
+        };
+        let s = serde_json::to_string(&b).unwrap();
+        digest::digest(&digest::SHA512, s.as_bytes()).as_ref().to_owned()
+    }
+}
+
+// a staking participant able to produce blocks once selected, holding its own
+// Ed25519 signer so the existing sign/verify machinery keeps working unchanged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Validator {
+    address: Vec<u8>,
+    stake: u64,
+    signer: Vec<u8>,
+}
+
+impl Validator {
+    fn new(stake: u64) -> Validator {
+        let rng = rand::SystemRandom::new();
+        let pkcs8_bytes = signature::Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key_pair = signature::Ed25519KeyPair::from_pkcs8(pkcs8_bytes.as_ref()).unwrap();
+        Validator {
+            address: key_pair.public_key().as_ref().to_owned(),
+            stake,
+            signer: pkcs8_bytes.as_ref().to_owned(),
+        }
+    }
+}
+
+// splitmix64, seeded from the previous block's hash - good enough to deterministically
+// draw a value in [0, total_stake) that every node can reproduce from the chain alone
+fn seeded_draw(seed_bytes: &[u8], modulus: u64) -> u64 {
+    if modulus == 0 { return 0; }
+    let mut seed = [0u8; 8];
+    let len = seed_bytes.len().min(8);
+    seed[..len].copy_from_slice(&seed_bytes[..len]);
+    let mut z = u64::from_be_bytes(seed).wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z = z ^ (z >> 31);
+    z % modulus
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StakePool {
+    validators: Vec<Validator>,
+}
+
+impl StakePool {
+    fn new(mut validators: Vec<Validator>) -> StakePool {
+        // canonical order so every node walks the stake in the same sequence
+        validators.sort_by(|a, b| a.address.cmp(&b.address));
+        StakePool { validators }
+    }
+
+    fn total_stake(&self) -> u64 {
+        self.validators.iter().map(|v| v.stake).sum()
+    }
+
+    // deterministically select the next block producer for `previous_hash`
+    fn select(&self, previous_hash: &[u8]) -> &Validator {
+        let draw = seeded_draw(previous_hash, self.total_stake());
+        let mut cumulative: u64 = 0;
+        for validator in &self.validators {
+            cumulative += validator.stake;
+            if draw < cumulative {
+                return validator;
+            }
+        }
+        self.validators.last().expect("stake pool has no validators")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ProofOfStake {
+    pool: StakePool,
+    block_chain: Vec<Block>,
+}
+
+impl ProofOfStake {
+    fn new(pool: StakePool) -> ProofOfStake {
+        let genesis = Block {
+            time: Utc::now(),
+            transactions: Vec::new(),
+            previous_hash: [].to_vec(),
+            validator: [].to_vec(),
+            sign: [].to_vec(),
+        };
+        ProofOfStake {
+            pool,
+            block_chain: vec![genesis],
+        }
+    }
+}
+
+trait BlockGenerator {
+    fn generate_block(&mut self, transactions: &[VerifiedTransaction]) -> Result<(), Error>;
+}
+
+impl BlockGenerator for ProofOfStake {
+    fn generate_block(&mut self, transactions: &[VerifiedTransaction]) -> Result<(), Error> {
+        let previous_hash = self.block_chain.last().unwrap().hash();
+        let producer = self.pool.select(&previous_hash);
+        let key_pair = signature::Ed25519KeyPair::from_pkcs8(producer.signer.as_ref())
+            .map_err(|_| anyhow!("invalid validator signer."))?;
+
+        let mut tlist = Vec::new();
+        tlist.extend_from_slice(transactions);
+
+        let block = Block {
+            time: Utc::now(),
+            transactions: tlist,
+            previous_hash,
+            validator: producer.address.clone(),
+            sign: [].to_vec(),
+        };
+        // sign the block as the selected validator
+        let s = serde_json::to_string(&block).unwrap();
+        let h = digest::digest(&digest::SHA512, s.as_bytes());
+        let sign = key_pair.sign(h.as_ref()).as_ref().to_vec();
+        let mut block = block;
+        block.sign = sign;
+        let block = block;
+        // publish the block
+        self.block_chain.push(block);
+        Ok(())
+    }
+}
+
+fn verify_block(previous_block: &Block, block: &Block, pool: &StakePool) -> bool {
+    let is_correct_hash = previous_block.hash() == block.previous_hash;
+    // re-run the same deterministic selection the producer used, so a block
+    // signed by anyone other than the expected validator is rejected
+    let expected_validator = pool.select(&block.previous_hash);
+    let is_expected_validator = block.validator == expected_validator.address;
+    let h = block.hash();
+    let peer_public_key = signature::UnparsedPublicKey::new(&signature::ED25519, &block.validator);
+    let is_valid_sign = peer_public_key.verify(h.as_ref(), &block.sign).is_ok();
+    is_correct_hash && is_expected_validator && is_valid_sign
+}
+
+fn verify_blockchain(chain: &[Block], pool: &StakePool) -> bool {
+    for i in 0..chain.len()-1 {
+        let index = chain.len()-i;
+        if !verify_block(&chain[index-2], &chain[index-1], pool) { return false; }
+    }
+    true
+}
+
+fn main() {
+    let pool = StakePool::new(vec![
+        Validator::new(50),
+        Validator::new(30),
+        Validator::new(20),
+    ]);
+    let mut proof_of_stake = ProofOfStake::new(pool);
+
+    let alice = Wallet::new();
+    let bob = Wallet::new();
+
+    let mut transactions = Vec::new();
+    transactions.push(verify_transaction(alice.send(&bob.address, 5.0)).unwrap());
+    transactions.push(verify_transaction(bob.send(&alice.address, 7.0)).unwrap());
+
+    proof_of_stake.generate_block(&transactions).unwrap();
+    proof_of_stake.generate_block(&transactions).unwrap();
+    proof_of_stake.generate_block(&transactions).unwrap();
+
+    let verify_result = verify_blockchain(&proof_of_stake.block_chain, &proof_of_stake.pool);
+    println!("proof of stake: {:?}", &proof_of_stake);
+    println!("verify: {:?}", verify_result);
+}
@@ -0,0 +1,210 @@
+extern crate anyhow;
+extern crate serde;
+extern crate ring;
+extern crate secp256k1;
+extern crate sha3;
+extern crate rand;
+
+use anyhow::{anyhow, Error, Result};
+use serde::{Deserialize, Serialize};
+use ring::{rand as ring_rand, digest};
+use ring::signature::{self, KeyPair};
+use secp256k1::{Secp256k1, SecretKey, PublicKey, Message};
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use sha3::{Digest as Sha3Digest, Keccak256};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+enum SignatureScheme {
+    Ed25519,
+    Secp256k1,
+}
+
+// the sender's identity is never carried on the transaction itself - it's
+// recovered from the signature (Ed25519 key match or secp256k1 address
+// recovery) and compared against an `expected_sender` supplied independently,
+// so there's no `sender_address` field here for a sender to forge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UnsignedTransaction {
+    receiver_address: Vec<u8>,
+    value: f32,
+}
+
+trait Transact {
+    fn str_data(&self) -> String;
+}
+
+impl Transact for UnsignedTransaction {
+    fn str_data(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SignedTransaction {
+    receiver_address: Vec<u8>,
+    value: f32,
+    scheme: SignatureScheme,
+    // Secp256k1: 64-byte r||s. Ed25519: the raw 64-byte signature.
+    sign: Vec<u8>,
+    // only set for SignatureScheme::Secp256k1
+    recovery_id: Option<u8>,
+}
+
+impl SignedTransaction {
+    fn unsigned(&self) -> UnsignedTransaction {
+        UnsignedTransaction {
+            receiver_address: self.receiver_address.clone(),
+            value: self.value,
+        }
+    }
+}
+
+// producible only by a successful verify_transaction, i.e. once the signer has
+// been independently confirmed - by Ed25519 key match or secp256k1 address recovery
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VerifiedTransaction(SignedTransaction);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Wallet {
+    scheme: SignatureScheme,
+    private_key: Vec<u8>,
+    address: Vec<u8>,
+}
+
+impl Wallet {
+    fn new_ed25519() -> Wallet {
+        let rng = ring_rand::SystemRandom::new();
+        let pkcs8_bytes = signature::Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key_pair = signature::Ed25519KeyPair::from_pkcs8(pkcs8_bytes.as_ref()).unwrap();
+        Wallet {
+            scheme: SignatureScheme::Ed25519,
+            private_key: pkcs8_bytes.as_ref().to_owned(),
+            address: key_pair.public_key().as_ref().to_owned(),
+        }
+    }
+
+    fn new_secp256k1() -> Wallet {
+        let secp = Secp256k1::new();
+        let (secret_key, public_key) = secp.generate_keypair(&mut rand::thread_rng());
+        Wallet {
+            scheme: SignatureScheme::Secp256k1,
+            private_key: secret_key.secret_bytes().to_vec(),
+            address: secp256k1_address(&public_key),
+        }
+    }
+}
+
+// à la ethers-rs Wallet: the address is the last 20 bytes of the Keccak-256
+// of the uncompressed public key, dropping its leading 0x04 tag byte
+fn secp256k1_address(public_key: &PublicKey) -> Vec<u8> {
+    let uncompressed = public_key.serialize_uncompressed();
+    let hash = Keccak256::digest(&uncompressed[1..]);
+    hash[12..].to_vec()
+}
+
+trait Deal {
+    fn sign_transaction(&self, transaction: &UnsignedTransaction) -> SignedTransaction;
+    fn send(&self, receiver_address: &[u8], value: f32) -> SignedTransaction;
+}
+
+impl Deal for Wallet {
+    fn sign_transaction(&self, transaction: &UnsignedTransaction) -> SignedTransaction {
+        match self.scheme {
+            SignatureScheme::Ed25519 => {
+                let key_pair = signature::Ed25519KeyPair::from_pkcs8(&self.private_key).unwrap();
+                let h = digest::digest(&digest::SHA512, &transaction.str_data().as_bytes());
+                SignedTransaction {
+                    receiver_address: transaction.receiver_address.clone(),
+                    value: transaction.value,
+                    scheme: SignatureScheme::Ed25519,
+                    sign: key_pair.sign(h.as_ref()).as_ref().to_vec(),
+                    recovery_id: None,
+                }
+            }
+            SignatureScheme::Secp256k1 => {
+                let secp = Secp256k1::new();
+                let secret_key = SecretKey::from_slice(&self.private_key).unwrap();
+                let h = Keccak256::digest(transaction.str_data().as_bytes());
+                let message = Message::from_digest_slice(&h).unwrap();
+                let recoverable = secp.sign_ecdsa_recoverable(&message, &secret_key);
+                let (recovery_id, sign) = recoverable.serialize_compact();
+                SignedTransaction {
+                    receiver_address: transaction.receiver_address.clone(),
+                    value: transaction.value,
+                    scheme: SignatureScheme::Secp256k1,
+                    sign: sign.to_vec(),
+                    recovery_id: Some(recovery_id.to_i32() as u8),
+                }
+            }
+        }
+    }
+
+    fn send(&self, receiver_address: &[u8], value: f32) -> SignedTransaction {
+        let t = UnsignedTransaction {
+            receiver_address: receiver_address.to_vec(),
+            value: value,
+        };
+        self.sign_transaction(&t)
+    }
+}
+
+// `expected_sender` is supplied independently of the transaction - there's no
+// `sender_address` field on the transaction itself to trust or forge
+fn verify_transaction(transaction: SignedTransaction, expected_sender: &[u8]) -> Result<VerifiedTransaction, Error> {
+    if transaction.sign.is_empty() { return Err(anyhow!("transaction's sign is empty.")); }
+
+    let unsigned = transaction.unsigned();
+    match transaction.scheme {
+        SignatureScheme::Ed25519 => {
+            let h = digest::digest(&digest::SHA512, &unsigned.str_data().as_bytes());
+            let peer_public_key = signature::UnparsedPublicKey::new(&signature::ED25519, expected_sender);
+            match peer_public_key.verify(h.as_ref(), &transaction.sign) {
+                Ok(_) => Ok(VerifiedTransaction(transaction)),
+                Err(_) => Err(anyhow!("invalid sign.")),
+            }
+        }
+        SignatureScheme::Secp256k1 => {
+            let recovery_id = transaction.recovery_id.ok_or_else(|| anyhow!("missing recovery id."))?;
+            let id = RecoveryId::from_i32(recovery_id as i32).map_err(|_| anyhow!("invalid recovery id."))?;
+            let sig = RecoverableSignature::from_compact(&transaction.sign, id)
+                .map_err(|_| anyhow!("invalid sign."))?;
+            let h = Keccak256::digest(unsigned.str_data().as_bytes());
+            let message = Message::from_digest_slice(&h).map_err(|_| anyhow!("invalid hash."))?;
+            let secp = Secp256k1::new();
+            let recovered_key = secp.recover_ecdsa(&message, &sig).map_err(|_| anyhow!("invalid sign."))?;
+            if secp256k1_address(&recovered_key) == expected_sender {
+                Ok(VerifiedTransaction(transaction))
+            } else {
+                Err(anyhow!("recovered signer does not match expected sender."))
+            }
+        }
+    }
+}
+
+fn main() {
+    let alice = Wallet::new_secp256k1();
+    let bob = Wallet::new_secp256k1();
+    let eve = Wallet::new_secp256k1();
+
+    let transaction = alice.send(&bob.address, 5.0);
+    match verify_transaction(transaction.clone(), &alice.address) {
+        Ok(_) => println!("valid: recovered signer matches alice."),
+        Err(e) => println!("invalid: {:?}", e),
+    }
+
+    // eve claiming alice's transaction as her own is rejected outright - the
+    // address is recovered from the signature itself, not read off the transaction
+    match verify_transaction(transaction, &eve.address) {
+        Ok(_) => println!("valid: recovered signer matches eve."),
+        Err(e) => println!("invalid: {:?}", e),
+    }
+
+    // the Ed25519 path keeps working unchanged, selected by the same enum
+    let carol = Wallet::new_ed25519();
+    let dave = Wallet::new_ed25519();
+    let ed25519_transaction = carol.send(&dave.address, 3.0);
+    match verify_transaction(ed25519_transaction, &carol.address) {
+        Ok(_) => println!("valid: ed25519 path still works."),
+        Err(e) => println!("invalid: {:?}", e),
+    }
+}
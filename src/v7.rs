@@ -0,0 +1,401 @@
+extern crate anyhow;
+extern crate serde;
+extern crate ring;
+extern crate chrono;
+
+use anyhow::{anyhow, Error, Result};
+use serde::{Deserialize, Serialize};
+use ring::{rand, digest};
+use ring::signature::{self, KeyPair};
+use chrono::{Utc, DateTime, Duration};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UnsignedTransaction {
+    sender_address: Vec<u8>,
+    receiver_address: Vec<u8>,
+    value: f32,
+}
+
+trait Transact {
+    fn str_data(&self) -> String;
+}
+
+impl Transact for UnsignedTransaction {
+    fn str_data(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SignedTransaction {
+    sender_address: Vec<u8>,
+    receiver_address: Vec<u8>,
+    value: f32,
+    sign: Vec<u8>,
+}
+
+impl SignedTransaction {
+    fn unsigned(&self) -> UnsignedTransaction {
+        UnsignedTransaction {
+            sender_address: self.sender_address.clone(),
+            receiver_address: self.receiver_address.clone(),
+            value: self.value,
+        }
+    }
+}
+
+// producible only by `verify_transaction` (a plain send) or `verify_claim` (an
+// htlc redeem/refund) - a block can only ever hold these
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VerifiedTransaction(SignedTransaction);
+
+// the locked side of an atomic swap: the receiver can claim `value` by revealing a
+// preimage that hashes to `hash_lock`, otherwise the sender reclaims it after `time_lock`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UnsignedHtlc {
+    sender_address: Vec<u8>,
+    receiver_address: Vec<u8>,
+    value: f32,
+    hash_lock: Vec<u8>,
+    time_lock: DateTime<Utc>,
+}
+
+impl Transact for UnsignedHtlc {
+    fn str_data(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HtlcTransaction {
+    sender_address: Vec<u8>,
+    receiver_address: Vec<u8>,
+    value: f32,
+    hash_lock: Vec<u8>,
+    time_lock: DateTime<Utc>,
+    sign: Vec<u8>,
+}
+
+impl HtlcTransaction {
+    fn unsigned(&self) -> UnsignedHtlc {
+        UnsignedHtlc {
+            sender_address: self.sender_address.clone(),
+            receiver_address: self.receiver_address.clone(),
+            value: self.value,
+            hash_lock: self.hash_lock.clone(),
+            time_lock: self.time_lock,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Wallet {
+    private_key: Vec<u8>,
+    address: Vec<u8>,
+}
+
+impl Wallet {
+    fn new() -> Wallet {
+        let rng = rand::SystemRandom::new();
+        let pkcs8_bytes = signature::Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key_pair = signature::Ed25519KeyPair::from_pkcs8(pkcs8_bytes.as_ref()).unwrap();
+        Wallet {
+            private_key: pkcs8_bytes.as_ref().to_owned(),
+            address: key_pair.public_key().as_ref().to_owned(),
+        }
+    }
+}
+
+trait Deal {
+    fn sign_transaction(&self, transaction: &UnsignedTransaction) -> SignedTransaction;
+    fn send(&self, receiver_address: &[u8], value: f32) -> SignedTransaction;
+}
+
+impl Deal for Wallet {
+    fn sign_transaction(&self, transaction: &UnsignedTransaction) -> SignedTransaction {
+        // generate signer from self private key
+        let key_pair = signature::Ed25519KeyPair::from_pkcs8(&self.private_key).unwrap();
+        // hash the transaction
+        let h = digest::digest(&digest::SHA512, &transaction.str_data().as_bytes());
+        SignedTransaction {
+            sender_address: transaction.sender_address.clone(),
+            receiver_address: transaction.receiver_address.clone(),
+            value: transaction.value,
+            sign: key_pair.sign(h.as_ref()).as_ref().to_vec(),
+        }
+    }
+
+    fn send(&self, receiver_address: &[u8], value: f32) -> SignedTransaction {
+        let t = UnsignedTransaction {
+            sender_address: self.address.clone(),
+            receiver_address: receiver_address.to_vec(),
+            value: value,
+        };
+        self.sign_transaction(&t)
+    }
+}
+
+// how long a lock stays claimable before the original sender can reclaim it
+const HTLC_TIMEOUT_MINUTES: i64 = 10;
+
+// how a `ClaimTransaction` settles its htlc: by revealing the preimage (redeem)
+// or by the original sender reclaiming the funds after the time lock (refund)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum ClaimWitness {
+    Preimage(Vec<u8>),
+    Timeout,
+}
+
+// a claim against a locked htlc. This is deliberately not a `SignedTransaction`:
+// the htlc's own Ed25519 signature was computed over `UnsignedHtlc`'s five fields,
+// not a plain send's three, so it can never re-verify against `verify_transaction`.
+// The claim's authority instead comes from the htlc's signature plus its witness,
+// both checked together by `verify_claim`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ClaimTransaction {
+    htlc: HtlcTransaction,
+    witness: ClaimWitness,
+}
+
+trait HashedTimelock {
+    fn lock(&self, receiver_address: &[u8], value: f32, secret: &[u8]) -> HtlcTransaction;
+    fn redeem(&self, htlc: &HtlcTransaction, preimage: &[u8]) -> Result<ClaimTransaction, Error>;
+    fn refund(&self, htlc: &HtlcTransaction) -> Result<ClaimTransaction, Error>;
+}
+
+impl HashedTimelock for Wallet {
+    fn lock(&self, receiver_address: &[u8], value: f32, secret: &[u8]) -> HtlcTransaction {
+        let unsigned = UnsignedHtlc {
+            sender_address: self.address.clone(),
+            receiver_address: receiver_address.to_vec(),
+            value: value,
+            hash_lock: digest::digest(&digest::SHA512, secret).as_ref().to_vec(),
+            time_lock: Utc::now() + Duration::minutes(HTLC_TIMEOUT_MINUTES),
+        };
+        let key_pair = signature::Ed25519KeyPair::from_pkcs8(&self.private_key).unwrap();
+        let h = digest::digest(&digest::SHA512, &unsigned.str_data().as_bytes());
+        HtlcTransaction {
+            sender_address: unsigned.sender_address,
+            receiver_address: unsigned.receiver_address,
+            value: unsigned.value,
+            hash_lock: unsigned.hash_lock,
+            time_lock: unsigned.time_lock,
+            sign: key_pair.sign(h.as_ref()).as_ref().to_vec(),
+        }
+    }
+
+    fn redeem(&self, htlc: &HtlcTransaction, preimage: &[u8]) -> Result<ClaimTransaction, Error> {
+        verify_htlc_lock(htlc)?;
+        let preimage_hash = digest::digest(&digest::SHA512, preimage).as_ref().to_vec();
+        if preimage_hash != htlc.hash_lock {
+            return Err(anyhow!("preimage does not match hash lock."));
+        }
+        Ok(ClaimTransaction {
+            htlc: htlc.clone(),
+            witness: ClaimWitness::Preimage(preimage.to_vec()),
+        })
+    }
+
+    fn refund(&self, htlc: &HtlcTransaction) -> Result<ClaimTransaction, Error> {
+        verify_htlc_lock(htlc)?;
+        if htlc.sender_address != self.address {
+            return Err(anyhow!("only the original sender may reclaim this lock."));
+        }
+        if Utc::now() <= htlc.time_lock {
+            return Err(anyhow!("time lock has not expired yet."));
+        }
+        Ok(ClaimTransaction {
+            htlc: htlc.clone(),
+            witness: ClaimWitness::Timeout,
+        })
+    }
+}
+
+fn verify_htlc_lock(htlc: &HtlcTransaction) -> Result<(), Error> {
+    if htlc.sign.is_empty() { return Err(anyhow!("htlc's sign is empty.")); }
+
+    let h = digest::digest(&digest::SHA512, &htlc.unsigned().str_data().as_bytes());
+    let peer_public_key = signature::UnparsedPublicKey::new(&signature::ED25519, &htlc.sender_address);
+    match peer_public_key.verify(h.as_ref(), &htlc.sign) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(anyhow!("invalid sign.")),
+    }
+}
+
+// the enforcement point for atomic swaps: a block can only ever hold a
+// `VerifiedTransaction`, and this is the only way to turn a `ClaimTransaction`
+// into one, so a claim that doesn't actually satisfy its htlc's hash-lock or
+// time-lock can never reach `generate_block`/`verify_block` in the first place
+fn verify_claim(claim: ClaimTransaction) -> Result<VerifiedTransaction, Error> {
+    verify_htlc_lock(&claim.htlc)?;
+    let (sender_address, receiver_address) = match &claim.witness {
+        ClaimWitness::Preimage(preimage) => {
+            let preimage_hash = digest::digest(&digest::SHA512, preimage).as_ref().to_vec();
+            if preimage_hash != claim.htlc.hash_lock {
+                return Err(anyhow!("preimage does not match hash lock."));
+            }
+            (claim.htlc.sender_address.clone(), claim.htlc.receiver_address.clone())
+        }
+        ClaimWitness::Timeout => {
+            if Utc::now() <= claim.htlc.time_lock {
+                return Err(anyhow!("time lock has not expired yet."));
+            }
+            (claim.htlc.sender_address.clone(), claim.htlc.sender_address.clone())
+        }
+    };
+    Ok(VerifiedTransaction(SignedTransaction {
+        sender_address,
+        receiver_address,
+        value: claim.htlc.value,
+        sign: claim.htlc.sign.clone(),
+    }))
+}
+
+fn verify_transaction(transaction: SignedTransaction) -> Result<VerifiedTransaction, Error> {
+    if transaction.sign.is_empty() { return Err(anyhow!("transaction's sign is empty.")); }
+
+    // hash the transaction
+    let h = digest::digest(&digest::SHA512, &transaction.unsigned().str_data().as_bytes());
+    // generate verifier with public key
+    let peer_public_key_bytes = &transaction.sender_address;
+    let peer_public_key = signature::UnparsedPublicKey::new(&signature::ED25519, peer_public_key_bytes);
+    // is the signature correct?
+    match peer_public_key.verify(h.as_ref(), &transaction.sign) {
+        Ok(_) => Ok(VerifiedTransaction(transaction)),
+        Err(_) => Err(anyhow!("invalid sign."))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Block {
+    time: DateTime<Utc>,
+    transactions: Vec<VerifiedTransaction>,
+    previous_hash: Vec<u8>,
+    sign: Vec<u8>,
+}
+
+trait HashBlock {
+    fn hash(&self) -> Vec<u8>;
+}
+
+impl HashBlock for Block {
+    fn hash(&self) -> Vec<u8> {
+        let b = Block {
+            time: self.time,
+            transactions: self.transactions.clone(),
+            previous_hash: self.previous_hash.clone(),
+            sign: [].to_vec(),
+        };
+        let s = serde_json::to_string(&b).unwrap();
+        digest::digest(&digest::SHA512, s.as_bytes()).as_ref().to_owned()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TimestampServer {
+    public_key: Vec<u8>,
+    block_chain: Vec<Block>,
+    signer: Vec<u8>,
+}
+
+impl TimestampServer {
+    fn new() -> TimestampServer {
+        let rng = rand::SystemRandom::new();
+        let pkcs8_bytes = signature::Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key_pair = signature::Ed25519KeyPair::from_pkcs8(pkcs8_bytes.as_ref()).unwrap();
+        let mut chain = Vec::new();
+        let genesis = Block {
+            time: Utc::now(),
+            transactions: Vec::new(),
+            previous_hash: [].to_vec(),
+            sign: [].to_vec(),
+        };
+        chain.push(genesis);
+        TimestampServer {
+            public_key: key_pair.public_key().as_ref().to_owned(),
+            block_chain: chain,
+            signer: pkcs8_bytes.as_ref().to_owned(),
+        }
+    }
+}
+
+trait BlockGenerator {
+    fn generate_block(&mut self, transactions: &[VerifiedTransaction]);
+}
+
+impl BlockGenerator for TimestampServer {
+    fn generate_block(&mut self, transactions: &[VerifiedTransaction]) {
+        let mut tlist = Vec::new();
+        tlist.extend_from_slice(transactions);
+        let tlist = tlist;
+        // generate block
+        let block = Block {
+            time: Utc::now(),
+            transactions: tlist,
+            previous_hash: self.block_chain.last().unwrap().hash(),
+            sign: [].to_vec(),
+        };
+        // sign the block
+        let key_pair = signature::Ed25519KeyPair::from_pkcs8(self.signer.as_ref()).unwrap();
+        let s = serde_json::to_string(&block).unwrap();
+        let h = digest::digest(&digest::SHA512, s.as_bytes());
+        let sign = key_pair.sign(h.as_ref()).as_ref().to_vec();
+        let mut block = block;
+        block.sign = sign;
+        let block = block;
+        // publish the block
+        self.block_chain.push(block);
+    }
+}
+
+fn verify_block(previous_block: &Block, block: &Block, timestamp_server_publickey: &[u8]) -> bool {
+    // every entry in `block.transactions` is already a `VerifiedTransaction`, so a
+    // claim that didn't satisfy its htlc's hash-lock/time-lock in `verify_claim`
+    // could never have become one - nothing left to re-check here.
+    let is_correct_hash = previous_block.hash() == block.previous_hash;
+    let h = block.hash();
+    let peer_public_key = signature::UnparsedPublicKey::new(&signature::ED25519, timestamp_server_publickey);
+    match peer_public_key.verify(h.as_ref(), &block.sign) {
+        Ok(_) => is_correct_hash,
+        Err(_) => false
+    }
+}
+
+fn verify_blockchain(chain: &[Block], timestamp_server_publickey: &[u8]) -> bool {
+    for i in 0..chain.len()-1 {
+        let index = chain.len()-i;
+        if !verify_block(&chain[index-2], &chain[index-1], timestamp_server_publickey) { return false; }
+    }
+    true
+}
+
+fn main() {
+    let mut timestamp_server = TimestampServer::new();
+
+    let alice = Wallet::new();
+    let bob = Wallet::new();
+
+    let secret = b"correct horse battery staple";
+    let htlc = alice.lock(&bob.address, 5.0, secret);
+    println!("htlc: {:?}", &htlc);
+
+    // bob reveals the preimage to claim the funds before the time lock expires
+    let claim = bob.redeem(&htlc, secret).unwrap();
+    timestamp_server.generate_block(&[verify_claim(claim).unwrap()]);
+
+    // redeeming with the wrong preimage is rejected
+    match bob.redeem(&htlc, b"wrong guess") {
+        Ok(_) => println!("unexpectedly redeemed with the wrong secret."),
+        Err(e) => println!("redeem rejected: {:?}", e),
+    }
+
+    // alice cannot reclaim the funds before the time lock has expired
+    match alice.refund(&htlc) {
+        Ok(_) => println!("unexpectedly refunded before the time lock expired."),
+        Err(e) => println!("refund rejected: {:?}", e),
+    }
+
+    let verify_result = verify_blockchain(&timestamp_server.block_chain, &timestamp_server.public_key);
+    println!("timestamp server: {:?}", &timestamp_server);
+    println!("verify: {:?}", verify_result);
+}
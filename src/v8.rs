@@ -0,0 +1,455 @@
+extern crate anyhow;
+extern crate serde;
+extern crate ring;
+extern crate chrono;
+extern crate tokio;
+extern crate bincode;
+
+use anyhow::{anyhow, Error, Result};
+use serde::{Deserialize, Serialize};
+use ring::{rand, digest};
+use ring::signature::{self, KeyPair};
+use chrono::{Utc, DateTime};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UnsignedTransaction {
+    sender_address: Vec<u8>,
+    receiver_address: Vec<u8>,
+    value: f32,
+}
+
+trait Transact {
+    fn str_data(&self) -> String;
+}
+
+impl Transact for UnsignedTransaction {
+    fn str_data(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SignedTransaction {
+    sender_address: Vec<u8>,
+    receiver_address: Vec<u8>,
+    value: f32,
+    sign: Vec<u8>,
+}
+
+impl SignedTransaction {
+    fn unsigned(&self) -> UnsignedTransaction {
+        UnsignedTransaction {
+            sender_address: self.sender_address.clone(),
+            receiver_address: self.receiver_address.clone(),
+            value: self.value,
+        }
+    }
+
+    // the deterministic bytes hashed into a block's Merkle root - bincode avoids
+    // the allocation and formatting non-determinism of round-tripping through JSON
+    fn canonical_bytes(&self) -> Vec<u8> {
+        #[derive(Serialize)]
+        struct Unsigned<'a> {
+            sender_address: &'a [u8],
+            receiver_address: &'a [u8],
+            value: f32,
+        }
+        bincode::serialize(&Unsigned {
+            sender_address: &self.sender_address,
+            receiver_address: &self.receiver_address,
+            value: self.value,
+        }).unwrap()
+    }
+}
+
+// producible only by a successful verify_transaction - gossiped transactions are
+// re-verified on arrival (see `Message::NewTransaction`) before entering a mempool,
+// so only these, never a raw `SignedTransaction` a peer handed us, can end up mined
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VerifiedTransaction(SignedTransaction);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Wallet {
+    private_key: Vec<u8>,
+    address: Vec<u8>,
+}
+
+impl Wallet {
+    fn new() -> Wallet {
+        let rng = rand::SystemRandom::new();
+        let pkcs8_bytes = signature::Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key_pair = signature::Ed25519KeyPair::from_pkcs8(pkcs8_bytes.as_ref()).unwrap();
+        Wallet {
+            private_key: pkcs8_bytes.as_ref().to_owned(),
+            address: key_pair.public_key().as_ref().to_owned(),
+        }
+    }
+}
+
+trait Deal {
+    fn sign_transaction(&self, transaction: &UnsignedTransaction) -> SignedTransaction;
+    fn send(&self, receiver_address: &[u8], value: f32) -> SignedTransaction;
+}
+
+impl Deal for Wallet {
+    fn sign_transaction(&self, transaction: &UnsignedTransaction) -> SignedTransaction {
+        // generate signer from self private key
+        let key_pair = signature::Ed25519KeyPair::from_pkcs8(&self.private_key).unwrap();
+        // hash the transaction
+        let h = digest::digest(&digest::SHA512, &transaction.str_data().as_bytes());
+        SignedTransaction {
+            sender_address: transaction.sender_address.clone(),
+            receiver_address: transaction.receiver_address.clone(),
+            value: transaction.value,
+            sign: key_pair.sign(h.as_ref()).as_ref().to_vec(),
+        }
+    }
+
+    fn send(&self, receiver_address: &[u8], value: f32) -> SignedTransaction {
+        let t = UnsignedTransaction {
+            sender_address: self.address.clone(),
+            receiver_address: receiver_address.to_vec(),
+            value: value,
+        };
+        self.sign_transaction(&t)
+    }
+}
+
+// shared by `verify_transaction` and `verify_block`: a block arriving over the
+// wire is only `Vec<VerifiedTransaction>` by type, not by any guarantee the peer
+// actually ran `verify_transaction` - so `verify_block` re-runs this same check
+// against every transaction a block claims to carry, rather than trusting the type
+fn is_validly_signed(transaction: &SignedTransaction) -> bool {
+    if transaction.sign.is_empty() { return false; }
+
+    let h = digest::digest(&digest::SHA512, &transaction.unsigned().str_data().as_bytes());
+    let peer_public_key = signature::UnparsedPublicKey::new(&signature::ED25519, &transaction.sender_address);
+    peer_public_key.verify(h.as_ref(), &transaction.sign).is_ok()
+}
+
+fn verify_transaction(transaction: SignedTransaction) -> Result<VerifiedTransaction, Error> {
+    if is_validly_signed(&transaction) {
+        Ok(VerifiedTransaction(transaction))
+    } else {
+        Err(anyhow!("invalid sign."))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Block {
+    time: DateTime<Utc>,
+    transactions: Vec<VerifiedTransaction>,
+    previous_hash: Vec<u8>,
+    nonce: u64,
+    difficulty: usize,
+    merkle_root: Vec<u8>,
+}
+
+impl Block {
+    // only the header: the transaction list itself is covered by `merkle_root`,
+    // so `mine`'s per-nonce hash no longer has to reserialize every transaction
+    fn canonical_bytes(&self) -> Vec<u8> {
+        #[derive(Serialize)]
+        struct Header<'a> {
+            time: DateTime<Utc>,
+            previous_hash: &'a [u8],
+            nonce: u64,
+            difficulty: usize,
+            merkle_root: &'a [u8],
+        }
+        bincode::serialize(&Header {
+            time: self.time,
+            previous_hash: &self.previous_hash,
+            nonce: self.nonce,
+            difficulty: self.difficulty,
+            merkle_root: &self.merkle_root,
+        }).unwrap()
+    }
+}
+
+trait HashBlock {
+    fn hash(&self) -> Vec<u8>;
+}
+
+impl HashBlock for Block {
+    fn hash(&self) -> Vec<u8> {
+        digest::digest(&digest::SHA512, &self.canonical_bytes()).as_ref().to_owned()
+    }
+}
+
+// the SHA-512 Merkle tree over a block's transactions, duplicating the last
+// leaf whenever a level has an odd number of nodes
+fn merkle_root(transactions: &[VerifiedTransaction]) -> Vec<u8> {
+    if transactions.is_empty() {
+        return digest::digest(&digest::SHA512, &[]).as_ref().to_owned();
+    }
+    let mut level: Vec<Vec<u8>> = transactions
+        .iter()
+        .map(|t| digest::digest(&digest::SHA512, &t.0.canonical_bytes()).as_ref().to_owned())
+        .collect();
+    while level.len() > 1 {
+        if level.len() % 2 == 1 {
+            level.push(level.last().unwrap().clone());
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| {
+                let mut combined = pair[0].clone();
+                combined.extend_from_slice(&pair[1]);
+                digest::digest(&digest::SHA512, &combined).as_ref().to_owned()
+            })
+            .collect();
+    }
+    level.remove(0)
+}
+
+// the lowest proof-of-work difficulty (in leading zero bits) this node will
+// accept from a peer - without a floor, a peer could set `difficulty: 0` and
+// mine blocks (and whole chains) for free, trivially winning the longest-chain race
+const MIN_DIFFICULTY: usize = 8;
+
+fn valid_proof(block: &Block) -> Result<(), Error> {
+    let h = block.hash();
+    let full_bytes = block.difficulty / 8;
+    let remaining_bits = block.difficulty % 8;
+    for i in 0..full_bytes {
+        if h[i] != 0 {
+            return Err(anyhow!("invalid nonce."));
+        }
+    }
+    if remaining_bits > 0 && h[full_bytes] >> (8 - remaining_bits) != 0 {
+        return Err(anyhow!("invalid nonce."));
+    }
+    Ok(())
+}
+
+fn mine(block: &Block) -> Block {
+    let mut nonce: u64 = 0;
+    // computed once up front - transactions don't change between nonce attempts,
+    // so the hash loop below only ever re-serializes the (small) block header
+    let merkle_root = merkle_root(&block.transactions);
+    let mut b = Block {
+        time: block.time,
+        transactions: block.transactions.clone(),
+        previous_hash: block.previous_hash.clone(),
+        nonce: nonce,
+        difficulty: block.difficulty,
+        merkle_root,
+    };
+    while valid_proof(&b).is_err() {
+        nonce += 1;
+        b.nonce = nonce;
+    }
+    b
+}
+
+fn verify_block(previous_block: &Block, block: &Block) -> bool {
+    let is_correct_hash = previous_block.hash() == block.previous_hash;
+    let is_sufficient_difficulty = block.difficulty >= MIN_DIFFICULTY;
+    let is_correct_transactions =
+        block.transactions
+        .iter()
+        .filter(|t| !is_validly_signed(&t.0))
+        .collect::<Vec<&VerifiedTransaction>>()
+        .is_empty();
+    let is_correct_merkle_root = merkle_root(&block.transactions) == block.merkle_root;
+    let is_correct_proof = valid_proof(block).is_ok();
+    is_correct_hash && is_sufficient_difficulty && is_correct_transactions && is_correct_merkle_root && is_correct_proof
+}
+
+fn verify_blockchain(chain: &[Block]) -> bool {
+    for i in 0..chain.len().saturating_sub(1) {
+        let index = chain.len()-i;
+        if !verify_block(&chain[index-2], &chain[index-1]) { return false; }
+    }
+    true
+}
+
+// the wire messages two nodes gossip over a length-prefixed TCP connection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Message {
+    NewTransaction(SignedTransaction),
+    NewBlock(Block),
+    GetChain,
+    ChainResponse(Vec<Block>),
+}
+
+async fn write_message(stream: &mut TcpStream, message: &Message) -> Result<()> {
+    let payload = bincode::serialize(message)?;
+    stream.write_all(&(payload.len() as u32).to_be_bytes()).await?;
+    stream.write_all(&payload).await?;
+    Ok(())
+}
+
+async fn read_message(stream: &mut TcpStream) -> Result<Message> {
+    let mut len_bytes = [0u8; 4];
+    stream.read_exact(&mut len_bytes).await?;
+    let mut payload = vec![0u8; u32::from_be_bytes(len_bytes) as usize];
+    stream.read_exact(&mut payload).await?;
+    Ok(bincode::deserialize(&payload)?)
+}
+
+// a chain plus the peers it gossips with and the transactions waiting to be mined
+#[derive(Clone)]
+struct Node {
+    chain: Arc<Mutex<Vec<Block>>>,
+    mempool: Arc<Mutex<Vec<VerifiedTransaction>>>,
+    peers: Arc<Mutex<Vec<String>>>,
+}
+
+impl Node {
+    fn new(genesis_difficulty: usize) -> Node {
+        let genesis = Block {
+            time: Utc::now(),
+            transactions: Vec::new(),
+            previous_hash: [].to_vec(),
+            nonce: 0,
+            difficulty: genesis_difficulty,
+            merkle_root: merkle_root(&[]),
+        };
+        Node {
+            chain: Arc::new(Mutex::new(vec![genesis])),
+            mempool: Arc::new(Mutex::new(Vec::new())),
+            peers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    async fn add_peer(&self, addr: &str) {
+        self.peers.lock().await.push(addr.to_owned());
+    }
+
+    async fn broadcast(&self, message: &Message) {
+        for peer in self.peers.lock().await.iter() {
+            if let Ok(mut stream) = TcpStream::connect(peer).await {
+                let _ = write_message(&mut stream, message).await;
+            }
+        }
+    }
+
+    async fn submit_transaction(&self, transaction: SignedTransaction) -> Result<()> {
+        let verified = verify_transaction(transaction.clone())?;
+        self.mempool.lock().await.push(verified);
+        self.broadcast(&Message::NewTransaction(transaction)).await;
+        Ok(())
+    }
+
+    async fn mine_block(&self, difficulty: usize) -> Block {
+        let pending: Vec<VerifiedTransaction> = self.mempool.lock().await.drain(..).collect();
+        let previous_hash = self.chain.lock().await.last().unwrap().hash();
+        let template = Block {
+            time: Utc::now(),
+            transactions: pending,
+            previous_hash,
+            nonce: 0,
+            difficulty,
+            merkle_root: Vec::new(),
+        };
+        let block = mine(&template);
+        self.adopt_block(block.clone()).await;
+        self.broadcast(&Message::NewBlock(block.clone())).await;
+        block
+    }
+
+    async fn adopt_block(&self, block: Block) -> bool {
+        let mut chain = self.chain.lock().await;
+        let accepted = verify_block(chain.last().unwrap(), &block);
+        if accepted {
+            chain.push(block);
+        }
+        accepted
+    }
+
+    // classic longest-valid-chain rule: only replace our chain with a candidate
+    // that is both longer and fully valid from genesis
+    async fn adopt_chain(&self, candidate: Vec<Block>) -> bool {
+        let mut chain = self.chain.lock().await;
+        let accepted = candidate.len() > chain.len() && verify_blockchain(&candidate);
+        if accepted {
+            *chain = candidate;
+        }
+        accepted
+    }
+
+    async fn handle_message(&self, message: Message) -> Option<Message> {
+        match message {
+            Message::NewTransaction(transaction) => {
+                if let Ok(verified) = verify_transaction(transaction) {
+                    self.mempool.lock().await.push(verified);
+                }
+                None
+            }
+            Message::NewBlock(block) => {
+                self.adopt_block(block).await;
+                None
+            }
+            Message::GetChain => {
+                Some(Message::ChainResponse(self.chain.lock().await.clone()))
+            }
+            Message::ChainResponse(candidate) => {
+                self.adopt_chain(candidate).await;
+                None
+            }
+        }
+    }
+
+    async fn request_chain(&self, peer: &str) -> Result<()> {
+        let mut stream = TcpStream::connect(peer).await?;
+        write_message(&mut stream, &Message::GetChain).await?;
+        if let Message::ChainResponse(candidate) = read_message(&mut stream).await? {
+            self.adopt_chain(candidate).await;
+        }
+        Ok(())
+    }
+
+    async fn listen(self, addr: &str) -> Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        loop {
+            let (mut socket, _) = listener.accept().await?;
+            let node = self.clone();
+            tokio::spawn(async move {
+                if let Ok(message) = read_message(&mut socket).await {
+                    if let Some(response) = node.handle_message(message).await {
+                        let _ = write_message(&mut socket, &response).await;
+                    }
+                }
+            });
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let alice = Wallet::new();
+    let bob = Wallet::new();
+
+    let node_a = Node::new(8);
+    let node_b = Node::new(8);
+
+    tokio::spawn(node_a.clone().listen("127.0.0.1:9001"));
+    tokio::spawn(node_b.clone().listen("127.0.0.1:9002"));
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+    node_a.add_peer("127.0.0.1:9002").await;
+    node_b.add_peer("127.0.0.1:9001").await;
+
+    // node_a receives a transaction and gossips it to node_b's mempool
+    node_a.submit_transaction(alice.send(&bob.address, 5.0)).await?;
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+    // node_a mines it into a block and gossips the block onward
+    let block = node_a.mine_block(8).await;
+    println!("node_a mined block: nonce = {:?}", block.nonce);
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+    // node_b asks node_a for its chain and adopts it under the longest-valid-chain rule
+    node_b.request_chain("127.0.0.1:9001").await?;
+
+    println!("node_a chain length: {:?}", node_a.chain.lock().await.len());
+    println!("node_b chain length: {:?}", node_b.chain.lock().await.len());
+
+    Ok(())
+}